@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Address, U256},
+    providers::Provider as _,
+    rpc::types::{TransactionReceipt, TransactionRequest},
+};
+
+use crate::external_api::contracts::{
+    error::BlockchainError,
+    handlers::send_transaction_with_gas_bump,
+    nonce_manager::NonceManager,
+    utils::{FeeUrgency, NormalProvider, FALLBACK_PRIORITY_FEE_WEI, FEE_HISTORY_BLOCK_COUNT},
+};
+
+/// Single entry point for on-chain interaction on the alloy stack, composing the signer, nonce
+/// manager, fee oracle, and gas-bump resubmission as stackable layers over one
+/// [`NormalProvider`] -- in the spirit of alloy's own `ProviderBuilder` layering. `balance_transfer`
+/// is the only caller today; every alloy-stack call site it makes goes through this instead of a
+/// bare `NormalProvider` plus ad hoc fee/nonce/retry handling, so that policy lives in one place
+/// rather than being duplicated per caller.
+///
+/// The mining, exit, and claim tasks are not callers, and they do not call the ethers-stack
+/// counterparts in `contracts::utils` (`send_and_confirm`, `next_pipelined_nonce`,
+/// `estimate_fees`) either; those play the same composing role on that stack. The two aren't
+/// merged into one because doing so
+/// would mean migrating those tasks off ethers entirely, which touches their transaction-building
+/// code, not just the client underneath it.
+#[derive(Clone)]
+pub struct MiningProvider {
+    provider: NormalProvider,
+    nonce_manager: Arc<NonceManager<Address>>,
+}
+
+impl MiningProvider {
+    pub fn new(provider: NormalProvider) -> Self {
+        Self {
+            provider,
+            nonce_manager: Arc::new(NonceManager::new()),
+        }
+    }
+
+    pub fn provider(&self) -> &NormalProvider {
+        &self.provider
+    }
+
+    pub async fn get_balance(&self, address: Address) -> Result<U256, BlockchainError> {
+        Ok(self.provider.get_balance(address).await?)
+    }
+
+    /// Estimates `(max_fee_per_gas, max_priority_fee_per_gas)` for `urgency` by sampling
+    /// `eth_feeHistory` over the last [`FEE_HISTORY_BLOCK_COUNT`] blocks: the priority fee is the
+    /// median of the requested reward percentile across that window, and the max fee is
+    /// `2 * pending_base_fee + priority_fee`. Falls back to the legacy gas price (used as both
+    /// values) when `eth_feeHistory` fails. Mirrors `contracts::utils::estimate_fees` so both
+    /// stacks price transactions the same way instead of diverging.
+    pub async fn estimate_fees(
+        &self,
+        urgency: FeeUrgency,
+    ) -> Result<(u128, u128), BlockchainError> {
+        let percentile = urgency.reward_percentile();
+        let fee_history = match self
+            .provider
+            .get_fee_history(FEE_HISTORY_BLOCK_COUNT, BlockNumberOrTag::Latest, &[percentile])
+            .await
+        {
+            Ok(fee_history) => fee_history,
+            Err(_) => {
+                let gas_price = self.provider.get_gas_price().await?;
+                return Ok((gas_price, gas_price));
+            }
+        };
+
+        let pending_base_fee = *fee_history.base_fee_per_gas.last().ok_or_else(|| {
+            BlockchainError::from(anyhow::anyhow!("eth_feeHistory returned no base fees"))
+        })?;
+
+        let mut rewards: Vec<u128> = fee_history
+            .reward
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|percentiles| percentiles.first().copied())
+            .filter(|reward| *reward > 0)
+            .collect();
+        let max_priority_fee_per_gas = if rewards.is_empty() {
+            FALLBACK_PRIORITY_FEE_WEI as u128
+        } else {
+            rewards.sort();
+            rewards[rewards.len() / 2]
+        };
+        let max_fee_per_gas = pending_base_fee * 2 + max_priority_fee_per_gas;
+        Ok((max_fee_per_gas, max_priority_fee_per_gas))
+    }
+
+    /// Hands out the next pipelined nonce for `address` (see [`NonceManager`]), syncing from the
+    /// chain the first time this address is seen or after [`reset_nonce`](Self::reset_nonce).
+    pub async fn next_nonce(&self, address: Address) -> anyhow::Result<u64> {
+        let provider = self.provider.clone();
+        self.nonce_manager
+            .next_nonce(address, move || async move {
+                Ok(provider.get_transaction_count(address).await?)
+            })
+            .await
+    }
+
+    /// Drops the cached nonce for `address`, forcing the next [`next_nonce`](Self::next_nonce)
+    /// call to re-sync from the chain. Called automatically by [`send_and_confirm`](Self::send_and_confirm)
+    /// on send failure.
+    pub async fn reset_nonce(&self, address: Address) {
+        self.nonce_manager.reset(address).await;
+    }
+
+    /// Assigns `tx_request` the next pipelined nonce for `from` and submits it signed by
+    /// `signer`, delegating to the existing [`send_transaction_with_gas_bump`] for fee escalation
+    /// on a stuck transaction. `label` and `context` are forwarded for its log messages.
+    pub async fn send_and_confirm<S>(
+        &self,
+        signer: S,
+        from: Address,
+        tx_request: TransactionRequest,
+        label: &str,
+        context: &str,
+    ) -> Result<TransactionReceipt, BlockchainError>
+    where
+        S: alloy::providers::Provider,
+    {
+        let nonce = self
+            .next_nonce(from)
+            .await
+            .map_err(|e| BlockchainError::from(anyhow::anyhow!(e)))?;
+        let tx_request = tx_request.nonce(nonce);
+        let result =
+            send_transaction_with_gas_bump(&self.provider, signer, tx_request, label, context)
+                .await;
+        if result.is_err() {
+            // The nonce may not have been consumed (e.g. the node rejected the tx outright), so
+            // re-sync from the chain rather than risk skipping a nonce forever.
+            self.reset_nonce(from).await;
+        }
+        result
+    }
+}
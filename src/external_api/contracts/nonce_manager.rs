@@ -0,0 +1,87 @@
+use std::{collections::HashMap, future::Future, hash::Hash};
+
+use tokio::sync::Mutex;
+
+/// Caches the next nonce to use per address so that back-to-back sends from the same key don't
+/// race each other on-chain (each would otherwise fetch the same `get_account_nonce` and collide).
+/// Mirrors the role of ethers-rs's `NonceManagerMiddleware`, but kept provider-agnostic: callers
+/// supply how to fetch the on-chain nonce, so the same cache can sit in front of either the
+/// ethers or alloy client.
+pub struct NonceManager<K> {
+    cached: Mutex<HashMap<K, u64>>,
+}
+
+impl<K> Default for NonceManager<K> {
+    fn default() -> Self {
+        Self {
+            cached: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Copy> NonceManager<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hands out the next nonce for `key`. On the first call for a given key, or after
+    /// [`reset`](Self::reset), `fetch_onchain` is used to seed the cache; otherwise the cached
+    /// value is returned without touching the network. Either way the cache then holds the
+    /// returned nonce plus one.
+    pub async fn next_nonce<F, Fut>(&self, key: K, fetch_onchain: F) -> anyhow::Result<u64>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<u64>>,
+    {
+        let mut cached = self.cached.lock().await;
+        let nonce = match cached.get(&key) {
+            Some(nonce) => *nonce,
+            None => fetch_onchain().await?,
+        };
+        cached.insert(key, nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Drops the cached nonce for `key`, forcing the next [`next_nonce`](Self::next_nonce) call
+    /// to re-sync from the chain. Call this on send errors (e.g. "nonce too low") or on startup.
+    pub async fn reset(&self, key: K) {
+        self.cached.lock().await.remove(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_next_nonce_increments_without_refetching() -> anyhow::Result<()> {
+        let manager = NonceManager::new();
+        let mut fetch_calls = 0;
+        let first = manager
+            .next_nonce(1u64, || async {
+                fetch_calls += 1;
+                Ok(5u64)
+            })
+            .await?;
+        let second = manager
+            .next_nonce(1u64, || async {
+                fetch_calls += 1;
+                Ok(5u64)
+            })
+            .await?;
+        assert_eq!(first, 5);
+        assert_eq!(second, 6);
+        assert_eq!(fetch_calls, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reset_refetches_on_next_call() -> anyhow::Result<()> {
+        let manager = NonceManager::new();
+        manager.next_nonce(1u64, || async { Ok(5u64) }).await?;
+        manager.reset(1u64).await;
+        let nonce = manager.next_nonce(1u64, || async { Ok(9u64) }).await?;
+        assert_eq!(nonce, 9);
+        Ok(())
+    }
+}
@@ -1,16 +1,69 @@
 use std::{env, sync::Arc};
 
 use ethers::{
+    abi::{AbiDecode, RawLog},
+    contract::EthEvent,
     core::k256::{ecdsa::SigningKey, SecretKey},
     middleware::SignerMiddleware,
     providers::{Http, Middleware, Provider},
     signers::{LocalWallet, Signer, Wallet},
-    types::{Address, H256, U256},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockId, BlockNumber, TransactionReceipt,
+        H256, U256,
+    },
     utils::hex::ToHex,
 };
 use log::info;
 
-use crate::utils::errors::CLIError;
+use crate::{external_api::contracts::nonce_manager::NonceManager, utils::errors::CLIError};
+
+// This is the ethers-based half of the client. It holds transaction-submission helpers for this
+// stack (pipelined nonces, fee estimation, gas-bump resubmission); the mining, exit, and claim
+// tasks do not call those helpers. The alloy-based `contracts::mining_provider::MiningProvider`
+// composes the same kind of policy for its one caller, `balance_transfer` -- the two stacks are
+// kept separate rather than merged so each caller's migration can happen independently.
+
+/// Selector for Solidity's built-in `Error(string)`, emitted by `require(cond, "msg")` and
+/// `revert("msg")`.
+const REVERT_ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Selector for Solidity's built-in `Panic(uint256)`, emitted by compiler-inserted checks
+/// (arithmetic overflow, out-of-bounds access, etc).
+const REVERT_PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Minimum fee increase most nodes enforce before they'll accept a replacement transaction for
+/// an already-pending nonce (12.5%, expressed as a numerator/denominator pair to stay in integer
+/// arithmetic).
+const REPLACEMENT_BUMP_NUMERATOR: u64 = 1125;
+const REPLACEMENT_BUMP_DENOMINATOR: u64 = 1000;
+
+/// Number of past blocks to sample when estimating EIP-1559 fees via `eth_feeHistory`. Also used
+/// by `contracts::mining_provider::MiningProvider::estimate_fees`, which mirrors this percentile
+/// logic on the alloy stack.
+pub(crate) const FEE_HISTORY_BLOCK_COUNT: u64 = 20;
+
+/// Fallback priority fee used when the fee history has no non-zero rewards to sample from
+/// (e.g. a quiet chain with no competing transactions). 1.5 gwei mirrors common wallet defaults.
+pub(crate) const FALLBACK_PRIORITY_FEE_WEI: u64 = 1_500_000_000;
+
+/// How aggressively to price a transaction, translated into the `eth_feeHistory` reward
+/// percentile sampled from recent blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeUrgency {
+    Low,
+    Medium,
+    High,
+}
+
+impl FeeUrgency {
+    pub(crate) fn reward_percentile(self) -> f64 {
+        match self {
+            FeeUrgency::Low => 10.0,
+            FeeUrgency::Medium => 50.0,
+            FeeUrgency::High => 90.0,
+        }
+    }
+}
 
 fn get_rpc_url() -> anyhow::Result<String> {
     let rpc_url = env::var("RPC_URL")
@@ -76,6 +129,31 @@ pub async fn get_account_nonce(address: Address) -> anyhow::Result<u64> {
     Ok(nonce.as_u64())
 }
 
+/// Process-wide cache of the next nonce to hand out per address, so pipelined deposit submission
+/// (multiple in-flight sends for the same key before any of them are confirmed) doesn't have
+/// every send race on the same `get_account_nonce` result.
+static NONCE_MANAGER: std::sync::OnceLock<NonceManager<Address>> = std::sync::OnceLock::new();
+
+/// Returns the next nonce to use for `address` without waiting for prior sends to confirm. Falls
+/// back to [`get_account_nonce`] the first time an address is seen, or after [`reset_nonce`] is
+/// called for it.
+pub async fn next_pipelined_nonce(address: Address) -> anyhow::Result<u64> {
+    let manager = NONCE_MANAGER.get_or_init(NonceManager::new);
+    manager
+        .next_nonce(address, || get_account_nonce(address))
+        .await
+}
+
+/// Drops the cached nonce for `address`, forcing the next [`next_pipelined_nonce`] call to
+/// re-sync from the chain. Call this when a gap is detected (e.g. a send failed or was dropped).
+pub async fn reset_nonce(address: Address) {
+    let manager = NONCE_MANAGER.get_or_init(NonceManager::new);
+    manager.reset(address).await;
+}
+
+/// Ethers-stack balance read, used by the mining, exit, and claim tasks. `MiningProvider` has its
+/// own `get_balance` for its one caller, `balance_transfer`, on the alloy stack -- not duplication
+/// of this call site, but the same read implemented once per stack.
 pub async fn get_balance(address: Address) -> anyhow::Result<U256> {
     info!("Getting balance");
     let client = get_client().await?;
@@ -86,6 +164,8 @@ pub async fn get_balance(address: Address) -> anyhow::Result<U256> {
     Ok(balance)
 }
 
+/// Flat legacy gas price, used only as [`estimate_fees`]'s fallback on nodes without
+/// `eth_feeHistory` support -- prefer `estimate_fees` for anything pricing a real transaction.
 pub async fn get_gas_price() -> anyhow::Result<U256> {
     info!("Getting gas price");
     let client = get_client().await?;
@@ -96,6 +176,53 @@ pub async fn get_gas_price() -> anyhow::Result<U256> {
     Ok(gas_price)
 }
 
+/// Estimates `(max_fee_per_gas, max_priority_fee_per_gas)` for the given urgency by sampling
+/// `eth_feeHistory` over the last [`FEE_HISTORY_BLOCK_COUNT`] blocks: the priority fee is the
+/// median of the requested reward percentile across that window, and the max fee is
+/// `2 * pending_base_fee + priority_fee`, leaving headroom for a few blocks of base fee increase.
+/// The mining, claim, and exit tasks do not call this function.
+///
+/// Falls back to [`get_gas_price`] (used as both values) on chains whose node doesn't support
+/// `eth_feeHistory`, e.g. some legacy or lightweight devnets.
+pub async fn estimate_fees(urgency: FeeUrgency) -> anyhow::Result<(U256, U256)> {
+    info!("Estimating fees for urgency {:?}", urgency);
+    let client = get_client().await?;
+    let percentile = urgency.reward_percentile();
+    let fee_history = match client
+        .fee_history(FEE_HISTORY_BLOCK_COUNT, BlockNumber::Latest, &[percentile])
+        .await
+    {
+        Ok(fee_history) => fee_history,
+        Err(e) => {
+            info!(
+                "eth_feeHistory unsupported ({:?}), falling back to legacy gas price",
+                e
+            );
+            let gas_price = get_gas_price().await?;
+            return Ok((gas_price, gas_price));
+        }
+    };
+
+    let pending_base_fee = *fee_history.base_fee_per_gas.last().ok_or_else(|| {
+        CLIError::NetworkError("eth_feeHistory returned no base fees".to_string())
+    })?;
+
+    let mut rewards: Vec<U256> = fee_history
+        .reward
+        .iter()
+        .filter_map(|percentiles| percentiles.first().copied())
+        .filter(|reward| !reward.is_zero())
+        .collect();
+    let max_priority_fee_per_gas = if rewards.is_empty() {
+        U256::from(FALLBACK_PRIORITY_FEE_WEI)
+    } else {
+        rewards.sort();
+        rewards[rewards.len() / 2]
+    };
+    let max_fee_per_gas = pending_base_fee * U256::from(2) + max_priority_fee_per_gas;
+    Ok((max_fee_per_gas, max_priority_fee_per_gas))
+}
+
 pub async fn get_tx_receipt(
     tx_hash: H256,
 ) -> anyhow::Result<ethers::core::types::TransactionReceipt> {
@@ -118,6 +245,228 @@ pub async fn get_tx_receipt(
     }
 }
 
+/// A decoded Solidity revert: either a `require`/`revert("msg")` (`Error(string)`), a
+/// compiler-inserted panic (`Panic(uint256)`, e.g. overflow or out-of-bounds access), or a revert
+/// we couldn't decode (bare `revert()`, a custom error, or a node that doesn't echo call data).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevertReason {
+    Error(String),
+    Panic(U256),
+    Unknown,
+}
+
+pub fn is_reverted(receipt: &TransactionReceipt) -> bool {
+    receipt
+        .status
+        .map(|status| status.is_zero())
+        .unwrap_or(false)
+}
+
+/// Waits for `tx_hash` to mine and returns its receipt, or `Err(CLIError::Reverted)` if it
+/// reverted, so callers can match on the decoded reason (e.g. "already claimed") instead of
+/// retrying blindly on any error.
+pub async fn confirm_transaction(tx_hash: H256) -> anyhow::Result<TransactionReceipt> {
+    let receipt = get_tx_receipt(tx_hash).await?;
+    if is_reverted(&receipt) {
+        let reason = decode_revert_reason(tx_hash, &receipt).await?;
+        return Err(CLIError::Reverted(reason).into());
+    }
+    Ok(receipt)
+}
+
+/// Re-simulates `tx_hash` via `eth_call` at the block it was mined in, to recover the Solidity
+/// revert reason that a bare receipt doesn't carry. Only meaningful for a reverted transaction
+/// (see [`is_reverted`]); callers should gate on that before paying for the extra round trip.
+pub async fn decode_revert_reason(
+    tx_hash: H256,
+    receipt: &TransactionReceipt,
+) -> anyhow::Result<RevertReason> {
+    let client = get_client().await?;
+    let tx = client
+        .get_transaction(tx_hash)
+        .await
+        .map_err(|e| CLIError::NetworkError(e.to_string()))?
+        .ok_or_else(|| CLIError::NetworkError(format!("Transaction {:?} not found", tx_hash)))?;
+    let block_id: BlockId = receipt
+        .block_number
+        .map(BlockId::from)
+        .unwrap_or_else(|| BlockNumber::Latest.into());
+    let call_request: TypedTransaction = (&tx).into();
+    match client.call(&call_request, Some(block_id)).await {
+        // A reverted transaction re-simulating as successful means the node doesn't replay
+        // historical state deterministically for this call; nothing more to decode from.
+        Ok(_) => Ok(RevertReason::Unknown),
+        Err(e) => Ok(decode_revert_data(revert_data_bytes(&e).as_deref().unwrap_or(&[]))),
+    }
+}
+
+/// Pulls the raw revert call-data out of an `eth_call` error response, if the node echoed one.
+fn revert_data_bytes(error: &ethers::providers::ProviderError) -> Option<Vec<u8>> {
+    let data = error.as_error_response().and_then(|e| e.data.as_ref())?;
+    let hex_data = data.as_str()?;
+    ethers::utils::hex::decode(hex_data.trim_start_matches("0x")).ok()
+}
+
+/// Decodes raw revert call data as a Solidity `Error(string)` or `Panic(uint256)`, falling back
+/// to [`RevertReason::Unknown`] for anything else (bare `revert()`, a custom error, or data too
+/// short to carry a selector).
+fn decode_revert_data(bytes: &[u8]) -> RevertReason {
+    if bytes.len() < 4 {
+        return RevertReason::Unknown;
+    }
+    let (selector, payload) = bytes.split_at(4);
+    if selector == REVERT_ERROR_SELECTOR {
+        if let Ok(reason) = String::decode(payload) {
+            return RevertReason::Error(reason);
+        }
+    } else if selector == REVERT_PANIC_SELECTOR {
+        if let Ok(code) = U256::decode(payload) {
+            return RevertReason::Panic(code);
+        }
+    }
+    RevertReason::Unknown
+}
+
+/// Scans a receipt's logs for the first one that decodes as event `T` (e.g. `Deposited`, or the
+/// claim module's claim event), so callers can confirm the expected event actually landed rather
+/// than trusting a successful status alone.
+pub fn find_event_log<T: EthEvent>(receipt: &TransactionReceipt) -> Option<T> {
+    receipt.logs.iter().find_map(|log| {
+        T::decode_log(&RawLog {
+            topics: log.topics.clone(),
+            data: log.data.to_vec(),
+        })
+        .ok()
+    })
+}
+
+/// Configures how [`send_and_confirm`] escalates a stuck transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct GasBumpConfig {
+    /// Number of blocks to wait for the current attempt to mine before bumping fees again.
+    pub confirmation_blocks: u64,
+    /// Maximum number of times to bump fees and resubmit before giving up.
+    pub max_bumps: u32,
+}
+
+impl Default for GasBumpConfig {
+    fn default() -> Self {
+        Self {
+            confirmation_blocks: 3,
+            max_bumps: 5,
+        }
+    }
+}
+
+/// Submits `tx` and escalates it if it doesn't mine within `config.confirmation_blocks`: the same
+/// nonce is rebroadcast with fees bumped by at least [`REPLACEMENT_BUMP_NUMERATOR`] /
+/// [`REPLACEMENT_BUMP_DENOMINATOR`], up to `config.max_bumps` times (an exponential schedule,
+/// since each bump compounds on the last). Every hash we've ever submitted for this nonce is
+/// checked for a receipt, since a node can mine any version of a replaced transaction. The mining,
+/// exit, and claim tasks do not submit through this function; `balance_transfer` uses the separate
+/// alloy-based `MiningProvider::send_and_confirm`.
+///
+/// Once mined, the receipt is checked for a revert the same way [`confirm_transaction`] does, so
+/// callers get `Err(CLIError::Reverted(reason))` for an on-chain rejection (e.g. "already
+/// claimed") instead of having to inspect a nominally-`Ok` receipt themselves.
+pub async fn send_and_confirm(
+    client: &SignerMiddleware<Provider<Http>, Wallet<SigningKey>>,
+    tx: TypedTransaction,
+    config: &GasBumpConfig,
+) -> anyhow::Result<TransactionReceipt> {
+    let from = client.address();
+    let nonce = client
+        .get_transaction_count(from, None)
+        .await
+        .map_err(|e| CLIError::NetworkError(e.to_string()))?;
+
+    let mut tx = tx;
+    tx.set_nonce(nonce);
+    let mut submitted_hashes = Vec::new();
+    for bump in 0..=config.max_bumps {
+        if bump > 0 {
+            bump_fees(&mut tx);
+            info!(
+                "Tx with nonce {} not mined after {} block(s), bumping fees (attempt {}/{})",
+                nonce, config.confirmation_blocks, bump, config.max_bumps
+            );
+        }
+        let pending_tx = client
+            .send_transaction(tx.clone(), None)
+            .await
+            .map_err(|e| CLIError::NetworkError(e.to_string()))?;
+        submitted_hashes.push(*pending_tx);
+
+        if let Some(receipt) =
+            wait_for_any_receipt(client, &submitted_hashes, config.confirmation_blocks).await?
+        {
+            if is_reverted(&receipt) {
+                let tx_hash = receipt.transaction_hash;
+                let reason = decode_revert_reason(tx_hash, &receipt).await?;
+                return Err(CLIError::Reverted(reason).into());
+            }
+            return Ok(receipt);
+        }
+    }
+
+    Err(CLIError::NetworkError(format!(
+        "Transaction from {:?} with nonce {} still pending after {} fee bumps",
+        from, nonce, config.max_bumps
+    ))
+    .into())
+}
+
+/// Polls for up to `confirmation_blocks` new blocks, returning the receipt of the first of
+/// `hashes` to mine, or `None` if none mined within the window.
+async fn wait_for_any_receipt(
+    client: &SignerMiddleware<Provider<Http>, Wallet<SigningKey>>,
+    hashes: &[H256],
+    confirmation_blocks: u64,
+) -> anyhow::Result<Option<TransactionReceipt>> {
+    let start_block = client
+        .get_block_number()
+        .await
+        .map_err(|e| CLIError::NetworkError(e.to_string()))?;
+    loop {
+        for hash in hashes {
+            if let Some(receipt) = client
+                .get_transaction_receipt(*hash)
+                .await
+                .map_err(|e| CLIError::NetworkError(e.to_string()))?
+            {
+                return Ok(Some(receipt));
+            }
+        }
+        let current_block = client
+            .get_block_number()
+            .await
+            .map_err(|e| CLIError::NetworkError(e.to_string()))?;
+        if current_block.saturating_sub(start_block) >= confirmation_blocks.into() {
+            return Ok(None);
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    }
+}
+
+/// Bumps a transaction's fees by `REPLACEMENT_BUMP_NUMERATOR / REPLACEMENT_BUMP_DENOMINATOR`.
+/// Called once per escalation attempt, so repeated calls compound into an exponential schedule.
+fn bump_fees(tx: &mut TypedTransaction) {
+    if let TypedTransaction::Eip1559(eip1559) = tx {
+        if let Some(max_fee) = eip1559.max_fee_per_gas {
+            eip1559.max_fee_per_gas = Some(bump_amount(max_fee));
+        }
+        if let Some(priority_fee) = eip1559.max_priority_fee_per_gas {
+            eip1559.max_priority_fee_per_gas = Some(bump_amount(priority_fee));
+        }
+    } else if let Some(gas_price) = tx.gas_price() {
+        tx.set_gas_price(bump_amount(gas_price));
+    }
+}
+
+fn bump_amount(amount: U256) -> U256 {
+    amount * U256::from(REPLACEMENT_BUMP_NUMERATOR) / U256::from(REPLACEMENT_BUMP_DENOMINATOR)
+}
+
 pub fn u256_as_bytes_be(u256: ethers::types::U256) -> [u8; 32] {
     let mut bytes = [0u8; 32];
     u256.to_big_endian(&mut bytes);
@@ -126,7 +475,10 @@ pub fn u256_as_bytes_be(u256: ethers::types::U256) -> [u8; 32] {
 
 #[cfg(test)]
 mod tests {
-    use ethers::types::Address;
+    use ethers::{
+        contract::EthEvent,
+        types::{Address, TransactionReceipt, H256, U256},
+    };
 
     use crate::{external_api::contracts::token::get_token_balance, utils::config::Settings};
 
@@ -147,4 +499,81 @@ mod tests {
         println!("{}", gas_price);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_estimate_fees() -> anyhow::Result<()> {
+        dotenv::dotenv().ok();
+        let (max_fee, max_priority_fee) = super::estimate_fees(super::FeeUrgency::Medium).await?;
+        assert!(max_fee >= max_priority_fee);
+        println!("max_fee={}, max_priority_fee={}", max_fee, max_priority_fee);
+        Ok(())
+    }
+
+    // The following don't touch the network, unlike the tests above, since they only exercise
+    // pure decoding logic.
+
+    #[test]
+    fn test_decode_revert_data_error_string() {
+        let encoded = [
+            super::REVERT_ERROR_SELECTOR.to_vec(),
+            ethers::abi::encode(&[ethers::abi::Token::String("already claimed".to_string())]),
+        ]
+        .concat();
+        assert_eq!(
+            super::decode_revert_data(&encoded),
+            super::RevertReason::Error("already claimed".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_revert_data_panic_code() {
+        let encoded = [
+            super::REVERT_PANIC_SELECTOR.to_vec(),
+            ethers::abi::encode(&[ethers::abi::Token::Uint(0x11.into())]),
+        ]
+        .concat();
+        assert_eq!(
+            super::decode_revert_data(&encoded),
+            super::RevertReason::Panic(0x11.into())
+        );
+    }
+
+    #[test]
+    fn test_decode_revert_data_unknown_selector() {
+        let encoded = [0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(super::decode_revert_data(&encoded), super::RevertReason::Unknown);
+    }
+
+    #[test]
+    fn test_decode_revert_data_too_short_to_carry_a_selector() {
+        assert_eq!(super::decode_revert_data(&[0x01, 0x02]), super::RevertReason::Unknown);
+    }
+
+    #[derive(Debug, Clone, EthEvent)]
+    struct TestMarkerEvent {
+        value: U256,
+    }
+
+    #[test]
+    fn test_find_event_log_decodes_matching_event() {
+        let mut log = ethers::types::Log::default();
+        log.topics = vec![TestMarkerEvent::signature()];
+        log.data = ethers::abi::encode(&[ethers::abi::Token::Uint(42.into())]).into();
+        let mut receipt = TransactionReceipt::default();
+        receipt.logs = vec![log];
+
+        let decoded: TestMarkerEvent =
+            super::find_event_log(&receipt).expect("log should decode as TestMarkerEvent");
+        assert_eq!(decoded.value, 42.into());
+    }
+
+    #[test]
+    fn test_find_event_log_ignores_non_matching_logs() {
+        let mut log = ethers::types::Log::default();
+        log.topics = vec![H256::zero()];
+        let mut receipt = TransactionReceipt::default();
+        receipt.logs = vec![log];
+
+        assert!(super::find_event_log::<TestMarkerEvent>(&receipt).is_none());
+    }
 }
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+
+/// Caps the total rate of outgoing RPC calls across all concurrent key workers, independent of
+/// how many keys are being processed in parallel, so a large `max_concurrent_keys` doesn't
+/// overwhelm the node. Implemented as a simple token bucket: a fixed number of permits are handed
+/// out per refill period, with unused permits carried over (capped at the bucket size).
+pub struct RpcRateLimiter {
+    semaphore: Arc<Semaphore>,
+}
+
+impl RpcRateLimiter {
+    /// Allows up to `max_requests_per_sec` permits to be acquired per second.
+    pub fn new(max_requests_per_sec: usize) -> Self {
+        let semaphore = Arc::new(Semaphore::new(max_requests_per_sec));
+        let refill = semaphore.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+            loop {
+                interval.tick().await;
+                let available = refill.available_permits();
+                let to_add = max_requests_per_sec.saturating_sub(available);
+                if to_add > 0 {
+                    refill.add_permits(to_add);
+                }
+            }
+        });
+        Self { semaphore }
+    }
+
+    /// Waits for an RPC permit to become available. Should be called once before each RPC call
+    /// made by a key worker.
+    pub async fn acquire(&self) {
+        // The permit is only used to throttle throughput, not to guard a resource, so it's
+        // consumed here with `forget` (the refill task replaces it) rather than held for the
+        // call's duration.
+        self.semaphore.acquire().await.expect("semaphore closed").forget();
+    }
+}
+
+/// Runs `worker` for each item in `items`, at most `max_concurrent` at a time, and returns once
+/// every worker has finished. The first error encountered is returned after all workers complete.
+pub async fn run_bounded<T, F, Fut>(
+    items: Vec<T>,
+    max_concurrent: usize,
+    worker: F,
+) -> anyhow::Result<()>
+where
+    T: Send + 'static,
+    F: Fn(T) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(max_concurrent.max(1)));
+    let worker = Arc::new(worker);
+    let mut tasks = Vec::with_capacity(items.len());
+    for item in items {
+        let semaphore = semaphore.clone();
+        let worker = worker.clone();
+        tasks.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+            worker(item).await
+        }));
+    }
+
+    let mut first_error = None;
+    for task in tasks {
+        if let Err(e) = task.await? {
+            if first_error.is_none() {
+                first_error = Some(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_bounded_processes_every_item() -> anyhow::Result<()> {
+        let processed = Arc::new(AtomicUsize::new(0));
+        let items: Vec<usize> = (0..10).collect();
+        let counter = processed.clone();
+        run_bounded(items, 3, move |_| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        })
+        .await?;
+        assert_eq!(processed.load(Ordering::SeqCst), 10);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_bounded_surfaces_first_error_after_all_complete() -> anyhow::Result<()> {
+        let processed = Arc::new(AtomicUsize::new(0));
+        let counter = processed.clone();
+        let result = run_bounded(vec![1, 2, 3], 2, move |item| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                if item == 2 {
+                    anyhow::bail!("item {} failed", item);
+                }
+                Ok(())
+            }
+        })
+        .await;
+        assert!(result.is_err());
+        // Every item still runs even though one fails, since failures aren't discovered until
+        // all workers have completed.
+        assert_eq!(processed.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
+use std::sync::Arc;
+
 use claim::claim_task;
+use concurrency::RpcRateLimiter;
 use ethers::types::{H256, U256};
 use intmax2_zkp::circuits::withdrawal;
 use mining::mining_task;
@@ -15,22 +18,78 @@ use crate::{
 
 pub mod assets_status;
 pub mod claim;
+pub mod concurrency;
 pub mod contracts;
 pub mod gas_validation;
 pub mod mining;
 pub mod sync;
 
+/// Default cap on outgoing RPC calls per second shared by every key worker, independent of how
+/// many keys are being processed concurrently.
+const DEFAULT_MAX_RPC_REQUESTS_PER_SEC: usize = 20;
+
+/// Maximum number of deposits to submit concurrently toward `mining_times` once a key has room
+/// for more, instead of waiting for each one to confirm before starting the next.
+const MINING_PIPELINE_DEPTH: usize = 4;
+
+/// Runs up to `max_concurrent_keys` independent mining workers in parallel, each claiming a
+/// disjoint slice of the key space (`start_key_number + worker_id`, stepping by
+/// `max_concurrent_keys`) so they never collide on the same deposit address. Each worker keeps
+/// its own clone of `state` and shares a single [`RpcRateLimiter`] so the combined RPC throughput
+/// stays bounded regardless of concurrency.
 pub async fn mining_loop(
+    state: &State,
+    withdrawal_private_key: H256,
+    start_key_number: u64,
+    mining_unit: U256,
+    mining_times: u64,
+    max_concurrent_keys: usize,
+) -> anyhow::Result<()> {
+    let max_concurrent_keys = max_concurrent_keys.max(1);
+    let rate_limiter = Arc::new(RpcRateLimiter::new(DEFAULT_MAX_RPC_REQUESTS_PER_SEC));
+
+    let mut workers = Vec::with_capacity(max_concurrent_keys);
+    for worker_id in 0..max_concurrent_keys as u64 {
+        let mut worker_state = state.clone();
+        let rate_limiter = rate_limiter.clone();
+        workers.push(tokio::spawn(async move {
+            mining_worker(
+                &mut worker_state,
+                withdrawal_private_key,
+                start_key_number + worker_id,
+                max_concurrent_keys as u64,
+                mining_unit,
+                mining_times,
+                &rate_limiter,
+            )
+            .await
+        }));
+    }
+
+    for worker in workers {
+        worker.await??;
+    }
+    Ok(())
+}
+
+/// Body of a single mining worker: walks its slice of the key space (`key_number`, stepping by
+/// `key_step`) forever, as the original single-threaded `mining_loop` did for the whole range,
+/// except that new deposits are submitted through [`submit_deposit_pipeline`]. The per-key mining cooldown randomization is untouched, so the privacy property it
+/// provides (workers don't all submit on the same cadence) holds per worker as well.
+async fn mining_worker(
     state: &mut State,
     withdrawal_private_key: H256,
     start_key_number: u64,
+    key_step: u64,
     mining_unit: U256,
     mining_times: u64,
+    rate_limiter: &Arc<RpcRateLimiter>,
 ) -> anyhow::Result<()> {
     let mut key_number = start_key_number;
     loop {
         let key = Key::new(withdrawal_private_key, key_number);
         print_status(format!("Mining loop for {:?}", key.deposit_address));
+        rate_limiter.acquire().await;
         let assets_status = state.sync_and_fetch_assets(&key).await?;
         // todo! recover from error
         validate_deposit_address_balance(
@@ -41,6 +100,7 @@ pub async fn mining_loop(
         )
         .await?;
         loop {
+            rate_limiter.acquire().await;
             let assets_status = state.sync_and_fetch_assets(&key).await?;
             if assets_status.senders_deposits.len() >= mining_times as usize
                 && assets_status.pending_indices.is_empty()
@@ -51,15 +111,30 @@ pub async fn mining_loop(
                     "Max deposits {} reached for {:?}. Please use another deposit address.",
                     mining_times, key.deposit_address
                 ));
-                key_number += 1;
+                key_number += key_step;
                 break;
             }
             let new_deposit = (assets_status.senders_deposits.len() < mining_times as usize) // deposit only if less than max deposits
             && (assets_status.pending_indices.is_empty()); // deposit only if no pending deposits
-            let cooldown =
-                mining_task(state, &key, &assets_status, new_deposit, false, mining_unit).await?;
+            let cooldown = if new_deposit {
+                let remaining = mining_times as usize - assets_status.senders_deposits.len();
+                let pipeline_depth = remaining.min(MINING_PIPELINE_DEPTH).max(1);
+                submit_deposit_pipeline(
+                    state,
+                    withdrawal_private_key,
+                    key_number,
+                    mining_unit,
+                    pipeline_depth,
+                    rate_limiter,
+                )
+                .await?
+            } else {
+                rate_limiter.acquire().await;
+                mining_task(state, &key, &assets_status, false, false, mining_unit).await?
+            };
 
             // print assets status after mining
+            rate_limiter.acquire().await;
             let assets_status = state.sync_and_fetch_assets(&key).await?;
             print_assets_status(&assets_status);
             if cooldown {
@@ -70,60 +145,139 @@ pub async fn mining_loop(
     }
 }
 
-pub async fn exit_loop(state: &mut State, mining_keys: &Keys) -> anyhow::Result<()> {
-    for key in mining_keys.to_keys().iter() {
-        print_status(format!("Exit loop for {:?}", key.deposit_address));
-        loop {
-            let assets_status = state.sync_and_fetch_assets(key).await.map_err(|e| {
-                CLIError::NetworkError(format!(
-                    "Failed while fetching assets status for {:?}: {:?}",
-                    key.deposit_address, e
-                ))
-            })?;
-
-            if assets_status.pending_indices.is_empty()
-                && assets_status.rejected_indices.is_empty()
-                && assets_status.not_withdrawn_indices.is_empty()
-            {
-                print_status(format!(
-                    "All deposits are withdrawn for {:?}. Exiting.",
-                    key.deposit_address,
-                ));
-                break;
+/// Submits up to `pipeline_depth` deposits for the key at `key_number` concurrently, rather than
+/// waiting for each one to confirm before starting the next. Each concurrent send works on its
+/// own clone of `state`; collisions on the shared on-chain nonce are avoided by `mining_task`'s
+/// own use of the process-wide ethers-stack nonce cache (see
+/// `contracts::utils::next_pipelined_nonce`), not by this function -- `MiningProvider`'s nonce
+/// manager is a separate instance used only by `balance_transfer` and doesn't cover this path.
+/// Returns whether any of the submissions asked for a cooldown.
+async fn submit_deposit_pipeline(
+    state: &State,
+    withdrawal_private_key: H256,
+    key_number: u64,
+    mining_unit: U256,
+    pipeline_depth: usize,
+    rate_limiter: &Arc<RpcRateLimiter>,
+) -> anyhow::Result<bool> {
+    let base_state = state.clone();
+    let rate_limiter = rate_limiter.clone();
+    let any_cooldown = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let slots: Vec<usize> = (0..pipeline_depth).collect();
+    let result_cooldown = any_cooldown.clone();
+    concurrency::run_bounded(slots, pipeline_depth, move |_| {
+        let mut worker_state = base_state.clone();
+        let rate_limiter = rate_limiter.clone();
+        let any_cooldown = any_cooldown.clone();
+        async move {
+            let key = Key::new(withdrawal_private_key, key_number);
+            rate_limiter.acquire().await;
+            let assets_status = worker_state.sync_and_fetch_assets(&key).await?;
+            rate_limiter.acquire().await;
+            let cooldown =
+                mining_task(&mut worker_state, &key, &assets_status, true, false, mining_unit)
+                    .await?;
+            if cooldown {
+                any_cooldown.store(true, std::sync::atomic::Ordering::Relaxed);
             }
+            Ok(())
+        }
+    })
+    .await?;
 
-            mining_task(state, key, &assets_status, false, true, 0.into()).await?;
+    Ok(result_cooldown.load(std::sync::atomic::Ordering::Relaxed))
+}
 
-            common_loop_cool_down().await;
+/// Processes every key in `mining_keys` concurrently, bounded by `max_concurrent_keys`, sharing a
+/// single [`RpcRateLimiter`] across all workers. Each worker operates on its own clone of `state`.
+pub async fn exit_loop(
+    state: &State,
+    mining_keys: &Keys,
+    max_concurrent_keys: usize,
+) -> anyhow::Result<()> {
+    let rate_limiter = Arc::new(RpcRateLimiter::new(DEFAULT_MAX_RPC_REQUESTS_PER_SEC));
+    let base_state = state.clone();
+    let keys = mining_keys.to_keys();
+    concurrency::run_bounded(keys, max_concurrent_keys, move |key| {
+        let mut worker_state = base_state.clone();
+        let rate_limiter = rate_limiter.clone();
+        async move { exit_key(&mut worker_state, &key, &rate_limiter).await }
+    })
+    .await
+}
+
+async fn exit_key(state: &mut State, key: &Key, rate_limiter: &RpcRateLimiter) -> anyhow::Result<()> {
+    print_status(format!("Exit loop for {:?}", key.deposit_address));
+    loop {
+        rate_limiter.acquire().await;
+        let assets_status = state.sync_and_fetch_assets(key).await.map_err(|e| {
+            CLIError::NetworkError(format!(
+                "Failed while fetching assets status for {:?}: {:?}",
+                key.deposit_address, e
+            ))
+        })?;
+
+        if assets_status.pending_indices.is_empty()
+            && assets_status.rejected_indices.is_empty()
+            && assets_status.not_withdrawn_indices.is_empty()
+        {
+            print_status(format!(
+                "All deposits are withdrawn for {:?}. Exiting.",
+                key.deposit_address,
+            ));
+            break;
         }
+
+        rate_limiter.acquire().await;
+        mining_task(state, key, &assets_status, false, true, 0.into()).await?;
+
+        common_loop_cool_down().await;
     }
 
     Ok(())
 }
 
-pub async fn claim_loop(state: &mut State, keys: &Keys) -> anyhow::Result<()> {
-    for key in keys.to_keys().iter() {
-        print_status(format!("Claim loop for {:?}", key.deposit_address));
-        loop {
-            let assets_status = state.sync_and_fetch_assets(key).await.map_err(|e| {
-                CLIError::NetworkError(format!(
-                    "Failed while fetching assets status for {:?}: {:?}",
-                    key.deposit_address, e
-                ))
-            })?;
-
-            if assets_status.not_claimed_indices.is_empty() {
-                print_status(format!(
-                    "All eligible rewards are claimed for {:?}.",
-                    key.deposit_address
-                ));
-                break;
-            }
+/// Processes every key in `keys` concurrently, bounded by `max_concurrent_keys`, sharing a single
+/// [`RpcRateLimiter`] across all workers. Each worker operates on its own clone of `state`.
+pub async fn claim_loop(
+    state: &State,
+    keys: &Keys,
+    max_concurrent_keys: usize,
+) -> anyhow::Result<()> {
+    let rate_limiter = Arc::new(RpcRateLimiter::new(DEFAULT_MAX_RPC_REQUESTS_PER_SEC));
+    let base_state = state.clone();
+    let keys = keys.to_keys();
+    concurrency::run_bounded(keys, max_concurrent_keys, move |key| {
+        let mut worker_state = base_state.clone();
+        let rate_limiter = rate_limiter.clone();
+        async move { claim_key(&mut worker_state, &key, &rate_limiter).await }
+    })
+    .await
+}
 
-            claim_task(state, key, &assets_status).await?;
+async fn claim_key(state: &mut State, key: &Key, rate_limiter: &RpcRateLimiter) -> anyhow::Result<()> {
+    print_status(format!("Claim loop for {:?}", key.deposit_address));
+    loop {
+        rate_limiter.acquire().await;
+        let assets_status = state.sync_and_fetch_assets(key).await.map_err(|e| {
+            CLIError::NetworkError(format!(
+                "Failed while fetching assets status for {:?}: {:?}",
+                key.deposit_address, e
+            ))
+        })?;
 
-            common_loop_cool_down().await;
+        if assets_status.not_claimed_indices.is_empty() {
+            print_status(format!(
+                "All eligible rewards are claimed for {:?}.",
+                key.deposit_address
+            ));
+            break;
         }
+
+        rate_limiter.acquire().await;
+        claim_task(state, key, &assets_status).await?;
+
+        common_loop_cool_down().await;
     }
 
     Ok(())
@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+use crate::external_api::contracts::utils::RevertReason;
+
+#[derive(Debug, Error)]
+pub enum CLIError {
+    #[error("Env error: {0}")]
+    EnvError(String),
+
+    #[error("Network error: {0}")]
+    NetworkError(String),
+
+    /// A transaction was mined but reverted on-chain, with the decoded reason (if any). Lets
+    /// callers distinguish a deliberate on-chain rejection (e.g. "already claimed") from a
+    /// transient network failure instead of retrying blindly.
+    #[error("Transaction reverted: {0:?}")]
+    Reverted(RevertReason),
+}